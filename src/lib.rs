@@ -11,25 +11,77 @@ mod ffi;
 mod utils;
 
 pub mod err {
+    /// A coarse classification of a failure, so downstream code can branch
+    /// without string-matching the VIPS message.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Kind {
+        Load,
+        Save,
+        Operation,
+        Nul,
+        Io,
+    }
+
+    /// A failure surfaced from a libvips call, carrying the operation that
+    /// failed and the parsed lines of the VIPS error buffer.
+    #[derive(Debug)]
+    pub struct Vips {
+        pub op: String,
+        pub messages: Vec<String>,
+    }
+
     #[derive(Debug)]
     pub enum Error {
-        Vips(Option<String>),
+        Vips(Vips),
         NulError(std::ffi::NulError),
         Io(std::io::Error),
         Boxed(Box<dyn std::error::Error + Send + Sync>),
     }
 
     impl Error {
-        pub(crate) fn from_vips() -> Self {
-            let out = unsafe {
+        pub(crate) fn from_vips(op: &str) -> Self {
+            let messages = unsafe {
                 let ptr = crate::ffi::vips_error_buffer();
-                if ptr.is_null() {
-                    None
+                let parsed = if ptr.is_null() {
+                    Vec::new()
                 } else {
-                    Some(std::ffi::CStr::from_ptr(ptr).to_string_lossy().into_owned())
-                }
+                    std::ffi::CStr::from_ptr(ptr)
+                        .to_string_lossy()
+                        .lines()
+                        .filter(|l| !l.trim().is_empty())
+                        .map(|l| l.to_owned())
+                        .collect()
+                };
+                // Clear the global buffer so stale lines from this failure never
+                // bleed into the next `from_vips`.
+                crate::ffi::vips_error_clear();
+                parsed
             };
-            Error::Vips(out)
+
+            Error::Vips(Vips { op: op.to_owned(), messages })
+        }
+
+        /// The category this error falls into.
+        pub fn kind(&self) -> Kind {
+            match self {
+                Error::Vips(v) => match v.op.as_str() {
+                    // Decoders, including the shrink-on-load thumbnailers whose
+                    // names carry neither "load" nor "from".
+                    "vips_image_new_from_file"
+                    | "vips_image_new_from_memory"
+                    | "vips_image_new_from_source"
+                    | "vips_thumbnail"
+                    | "vips_thumbnail_buffer" => Kind::Load,
+                    // Encoders.
+                    "vips_image_write_to_file"
+                    | "vips_image_write_to_buffer"
+                    | "vips_image_write_to_target" => Kind::Save,
+                    _ => Kind::Operation,
+                },
+                Error::NulError(_) => Kind::Nul,
+                Error::Io(_) => Kind::Io,
+                Error::Boxed(_) => Kind::Operation,
+            }
         }
     }
 
@@ -38,9 +90,9 @@ pub mod err {
             use Error::*;
             match self {
                 Vips(ref e) => {
-                    let msg =
-                        e.as_ref().map(|x| x.as_str()).unwrap_or_else(|| "Unknown VIPS error");
-                    write!(f, "{}", msg)?;
+                    let detail = e.messages.join("; ");
+                    let detail = if detail.is_empty() { "Unknown VIPS error" } else { &detail };
+                    write!(f, "{}: {}", e.op, detail)?;
                 }
                 NulError(ref e) => {
                     write!(f, "{}", e)?;
@@ -63,7 +115,16 @@ pub mod err {
         }
     }
 
-    impl std::error::Error for Error {}
+    impl std::error::Error for Error {
+        fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+            match self {
+                Error::NulError(ref e) => Some(e),
+                Error::Io(ref e) => Some(e),
+                Error::Boxed(ref e) => Some(&**e),
+                Error::Vips(_) => None,
+            }
+        }
+    }
 
     pub type Result<T> = std::result::Result<T, Error>;
 }
@@ -72,7 +133,9 @@ use std::ffi::{CStr, CString};
 use std::os::raw::{c_char, c_int, c_void};
 use std::ptr;
 
-pub use ffi::{VipsAngle, VipsBandFormat, VipsKernel};
+pub use ffi::{
+    VipsAngle, VipsBandFormat, VipsInteresting, VipsInterpretation, VipsKernel, VipsSize,
+};
 
 const NULL_TERM: *const c_char = ptr::null();
 
@@ -133,6 +196,34 @@ fn initialize() {
     initialize_with_maybe_options(None)
 }
 
+/// Options for [`Image::thumbnail`] / [`Image::thumbnail_buffer`]. Left
+/// unset, each falls back to the `vips_thumbnail` default.
+#[derive(Default)]
+pub struct ThumbnailOptions {
+    size: Option<VipsSize>,
+    crop: Option<VipsInteresting>,
+    auto_rotate: Option<bool>,
+}
+
+impl ThumbnailOptions {
+    /// How the target dimensions are applied: down-only, up, or force.
+    pub fn with_size(mut self, size: VipsSize) -> Self {
+        self.size = Some(size);
+        self
+    }
+    /// Saliency-aware crop strategy (`centre`, `entropy`, `attention`, …) used
+    /// to reach an exact width × height.
+    pub fn with_crop(mut self, crop: VipsInteresting) -> Self {
+        self.crop = Some(crop);
+        self
+    }
+    /// Honor the EXIF `orientation` tag while shrinking.
+    pub fn with_auto_rotate(mut self, on: bool) -> Self {
+        self.auto_rotate = Some(on);
+        self
+    }
+}
+
 pub struct Image(*mut ffi::VipsImage);
 
 impl Drop for Image {
@@ -156,13 +247,95 @@ impl Image {
         unsafe { ffi::vips_image_get_height(self.0) }
     }
 
+    pub fn bands(&self) -> i32 {
+        unsafe { ffi::vips_image_get_bands(self.0) }
+    }
+
+    pub fn format(&self) -> VipsBandFormat {
+        unsafe { ffi::vips_image_get_format(self.0) }
+    }
+
+    pub fn interpretation(&self) -> VipsInterpretation {
+        unsafe { ffi::vips_image_get_interpretation(self.0) }
+    }
+
+    /// Whether the header carries a field under `field`.
+    pub fn has_field<S: Into<Vec<u8>>>(&self, field: S) -> err::Result<bool> {
+        let field = CString::new(field)?;
+        Ok(unsafe { ffi::vips_image_get_typeof(self.0, field.as_ptr()) } != 0)
+    }
+
+    pub fn get_int<S: Into<Vec<u8>>>(&self, field: S) -> err::Result<i32> {
+        let field = CString::new(field)?;
+        let mut out: c_int = 0;
+        let ret = unsafe { ffi::vips_image_get_int(self.0, field.as_ptr(), &mut out) };
+        match ret {
+            0 => Ok(out),
+            _ => Err(err::Error::from_vips("vips_image_get_int")),
+        }
+    }
+
+    pub fn get_double<S: Into<Vec<u8>>>(&self, field: S) -> err::Result<f64> {
+        let field = CString::new(field)?;
+        let mut out: f64 = 0.0;
+        let ret = unsafe { ffi::vips_image_get_double(self.0, field.as_ptr(), &mut out) };
+        match ret {
+            0 => Ok(out),
+            _ => Err(err::Error::from_vips("vips_image_get_double")),
+        }
+    }
+
+    pub fn get_string<S: Into<Vec<u8>>>(&self, field: S) -> err::Result<String> {
+        let field = CString::new(field)?;
+        let mut out: *const c_char = ptr::null();
+        let ret = unsafe { ffi::vips_image_get_string(self.0, field.as_ptr(), &mut out) };
+        match ret {
+            0 => {
+                // The image owns the storage; copy it out before returning.
+                let s = unsafe { CStr::from_ptr(out).to_string_lossy().into_owned() };
+                Ok(s)
+            }
+            _ => Err(err::Error::from_vips("vips_image_get_string")),
+        }
+    }
+
+    /// Read an opaque blob field (ICC profile, raw EXIF, …) as owned bytes.
+    pub fn get_blob<S: Into<Vec<u8>>>(&self, field: S) -> err::Result<Vec<u8>> {
+        let field = CString::new(field)?;
+        let mut data: *const c_void = ptr::null();
+        let mut size = 0usize;
+        let ret =
+            unsafe { ffi::vips_image_get_blob(self.0, field.as_ptr(), &mut data, &mut size) };
+        match ret {
+            0 => {
+                if size == 0 {
+                    return Ok(Vec::new());
+                }
+                let slice = unsafe { std::slice::from_raw_parts(data as *const u8, size) };
+                Ok(slice.to_vec())
+            }
+            _ => Err(err::Error::from_vips("vips_image_get_blob")),
+        }
+    }
+
+    /// Apply the rotation implied by the EXIF `orientation` header and strip the
+    /// tag — the standard fix for sideways phone photos.
+    pub fn autorot(&self) -> err::Result<Image> {
+        let mut out = Image::new();
+        let ret = unsafe { ffi::vips_autorot(self.0, &mut out.0, NULL_TERM) };
+        match ret {
+            0 => Ok(out),
+            _ => Err(err::Error::from_vips("vips_autorot")),
+        }
+    }
+
     pub fn from_file<S: Into<Vec<u8>>>(path: S) -> err::Result<Image> {
         initialize();
 
         let path = CString::new(path)?;
         let ptr = unsafe { ffi::vips_image_new_from_file(path.as_ptr(), NULL_TERM) };
         if ptr.is_null() {
-            Err(err::Error::from_vips())
+            Err(err::Error::from_vips("vips_image_new_from_file"))
         } else {
             Ok(Image(ptr))
         }
@@ -212,12 +385,295 @@ impl Image {
         };
 
         if ptr.is_null() {
-            Err(err::Error::from_vips())
+            Err(err::Error::from_vips("vips_image_new_from_memory"))
+        } else {
+            Ok(Image(ptr))
+        }
+    }
+
+    pub fn from_reader<R: std::io::Read + std::io::Seek + 'static>(reader: R) -> err::Result<Image> {
+        initialize();
+
+        // State boxed behind the source so the Rust reader outlives every lazy
+        // pull libvips makes, plus a slot to stash any `io::Error` raised inside
+        // a callback rather than let it unwind across the FFI boundary.
+        struct State<R> {
+            reader: R,
+            error: Option<std::io::Error>,
+        }
+
+        unsafe extern "C" fn read_cb<R: std::io::Read + std::io::Seek>(
+            _source: *mut ffi::VipsSourceCustom,
+            buffer: *mut c_void,
+            length: i64,
+            user_data: *mut c_void,
+        ) -> i64 {
+            let state = &mut *(user_data as *mut State<R>);
+            // A non-positive request asks for no bytes; that is not EOF, so hand
+            // the reader an empty slice and report the zero it actually read.
+            let len = if length < 0 { 0 } else { length as usize };
+            let buf = std::slice::from_raw_parts_mut(buffer as *mut u8, len);
+            match state.reader.read(buf) {
+                Ok(n) => n as i64,
+                Err(e) => {
+                    state.error = Some(e);
+                    -1
+                }
+            }
+        }
+
+        unsafe extern "C" fn seek_cb<R: std::io::Read + std::io::Seek>(
+            _source: *mut ffi::VipsSourceCustom,
+            offset: i64,
+            whence: c_int,
+            user_data: *mut c_void,
+        ) -> i64 {
+            use std::io::SeekFrom;
+            let state = &mut *(user_data as *mut State<R>);
+            let from = match whence {
+                0 => SeekFrom::Start(offset as u64),
+                1 => SeekFrom::Current(offset),
+                2 => SeekFrom::End(offset),
+                _ => return -1,
+            };
+            match state.reader.seek(from) {
+                Ok(pos) => pos as i64,
+                Err(e) => {
+                    state.error = Some(e);
+                    -1
+                }
+            }
+        }
+
+        unsafe extern "C" fn drop_state<R>(data: *mut c_void) {
+            drop(Box::from_raw(data as *mut State<R>));
+        }
+
+        let state = Box::new(State { reader, error: None });
+        let raw: *mut State<R> = Box::into_raw(state);
+
+        let (ptr, source) = unsafe {
+            let source = ffi::vips_source_custom_new();
+
+            let read_cb: unsafe extern "C" fn() =
+                std::mem::transmute(read_cb::<R> as *const ());
+            ffi::g_signal_connect_data(
+                source as *mut c_void,
+                "read\0".as_ptr() as *const c_char,
+                Some(read_cb),
+                raw as *mut c_void,
+                Some(drop_state::<R>),
+                ffi::GConnectFlags::G_CONNECT_AFTER,
+            );
+
+            let seek_cb: unsafe extern "C" fn() =
+                std::mem::transmute(seek_cb::<R> as *const ());
+            ffi::g_signal_connect_data(
+                source as *mut c_void,
+                "seek\0".as_ptr() as *const c_char,
+                Some(seek_cb),
+                raw as *mut c_void,
+                None,
+                ffi::GConnectFlags::G_CONNECT_AFTER,
+            );
+
+            let image = ffi::vips_image_new_from_source(
+                source as *mut ffi::VipsSource,
+                "\0".as_ptr() as *const c_char,
+                NULL_TERM,
+            );
+
+            (image, source)
+        };
+
+        // Read the error out before the source (and boxed reader, via
+        // `drop_state`) is freed: on the NULL path the image holds no ref, so
+        // this unref finalizes the source and frees the `State` behind `raw`.
+        let io_error = unsafe { &mut *raw }.error.take();
+
+        // The image took its own ref on the source; drop ours so the source
+        // (and the boxed reader, via `drop_state`) is freed with the image.
+        unsafe { ffi::g_object_unref(source as *mut c_void) };
+
+        if ptr.is_null() {
+            // Surface a stashed reader error in preference to the VIPS buffer.
+            match io_error {
+                Some(e) => Err(err::Error::Io(e)),
+                None => Err(err::Error::from_vips("vips_image_new_from_source")),
+            }
         } else {
             Ok(Image(ptr))
         }
     }
 
+    pub fn write_to_writer<W: std::io::Write + 'static>(&self, writer: W, suffix: &str) -> err::Result<()> {
+        struct State<W> {
+            writer: W,
+            error: Option<std::io::Error>,
+        }
+
+        unsafe extern "C" fn write_cb<W: std::io::Write>(
+            _target: *mut ffi::VipsTargetCustom,
+            buffer: *const c_void,
+            length: i64,
+            user_data: *mut c_void,
+        ) -> i64 {
+            let state = &mut *(user_data as *mut State<W>);
+            if length <= 0 {
+                return 0;
+            }
+            let buf = std::slice::from_raw_parts(buffer as *const u8, length as usize);
+            match state.writer.write_all(buf) {
+                Ok(()) => length,
+                Err(e) => {
+                    state.error = Some(e);
+                    -1
+                }
+            }
+        }
+
+        unsafe extern "C" fn drop_state<W>(data: *mut c_void) {
+            drop(Box::from_raw(data as *mut State<W>));
+        }
+
+        let suffix = CString::new(String::from(suffix))?;
+
+        let state = Box::new(State { writer, error: None });
+        let raw: *mut State<W> = Box::into_raw(state);
+
+        let (ret, target) = unsafe {
+            let target = ffi::vips_target_custom_new();
+
+            let write_cb: unsafe extern "C" fn() =
+                std::mem::transmute(write_cb::<W> as *const ());
+            ffi::g_signal_connect_data(
+                target as *mut c_void,
+                "write\0".as_ptr() as *const c_char,
+                Some(write_cb),
+                raw as *mut c_void,
+                Some(drop_state::<W>),
+                ffi::GConnectFlags::G_CONNECT_AFTER,
+            );
+
+            let ret = ffi::vips_image_write_to_target(
+                self.0,
+                suffix.as_ptr(),
+                target as *mut ffi::VipsTarget,
+                NULL_TERM,
+            );
+
+            (ret, target)
+        };
+
+        // Read the error out before the target (and boxed writer) is freed.
+        let io_error = unsafe { &mut *raw }.error.take();
+        unsafe { ffi::g_object_unref(target as *mut c_void) };
+
+        match ret {
+            0 => Ok(()),
+            _ => match io_error {
+                Some(e) => Err(err::Error::Io(e)),
+                None => Err(err::Error::from_vips("vips_image_write_to_target")),
+            },
+        }
+    }
+
+    pub fn thumbnail<S: Into<Vec<u8>>>(
+        path: S,
+        width: i32,
+        height: Option<i32>,
+        opts: ThumbnailOptions,
+    ) -> err::Result<Image> {
+        initialize();
+
+        let path = CString::new(path)?;
+        let mut out = Image::new();
+
+        let ThumbnailOptions { size, crop, auto_rotate } = opts;
+        let no_rotate = auto_rotate.map(|a| !a);
+
+        let ret = unsafe {
+            var_args!(ffi::vips_thumbnail,
+                args => [path.as_ptr(), &mut out.0, width,],
+                opts => [
+                    (height, height, "height\0".as_ptr(), height),
+                    (crop, crop, "crop\0".as_ptr(), crop),
+                    (size, size, "size\0".as_ptr(), size),
+                    (no_rotate, no_rotate as c_int, "no-rotate\0".as_ptr(), no_rotate),
+                ],
+                term => NULL_TERM)
+        };
+
+        match ret {
+            0 => Ok(out),
+            _ => Err(err::Error::from_vips("vips_thumbnail")),
+        }
+    }
+
+    pub fn thumbnail_buffer(
+        buf: Vec<u8>,
+        width: i32,
+        height: Option<i32>,
+        opts: ThumbnailOptions,
+    ) -> err::Result<Image> {
+        pub unsafe extern "C" fn post_close(_: *mut ffi::VipsImage, user_data: *mut c_void) {
+            let buf = Box::from_raw(user_data as *mut Box<[u8]>);
+            drop(buf);
+        }
+
+        initialize();
+
+        let mut out = Image::new();
+
+        let ThumbnailOptions { size, crop, auto_rotate } = opts;
+        let no_rotate = auto_rotate.map(|a| !a);
+
+        let buf = buf.into_boxed_slice();
+
+        let ret = unsafe {
+            var_args!(ffi::vips_thumbnail_buffer,
+                args => [buf.as_ptr() as *mut c_void, buf.len(), &mut out.0, width,],
+                opts => [
+                    (height, height, "height\0".as_ptr(), height),
+                    (crop, crop, "crop\0".as_ptr(), crop),
+                    (size, size, "size\0".as_ptr(), size),
+                    (no_rotate, no_rotate as c_int, "no-rotate\0".as_ptr(), no_rotate),
+                ],
+                term => NULL_TERM)
+        };
+
+        // `vips_thumbnail_buffer` builds a lazy pipeline on top of a
+        // `*_buffer` loader, which keeps reading from `buf` as pixels are
+        // demanded by later ops on `out` — not just during this call. Hand
+        // it to the output image via a `postclose` handler, same as
+        // `from_memory`, instead of freeing it here.
+        let buf = Box::new(buf);
+        let raw: *mut c_void = Box::into_raw(buf) as *mut c_void;
+
+        if ret == 0 {
+            unsafe {
+                let callback: unsafe extern "C" fn() =
+                    std::mem::transmute(post_close as *const ());
+                ffi::g_signal_connect_data(
+                    out.0 as *mut c_void,
+                    "postclose\0".as_ptr() as *const c_char,
+                    Some(callback),
+                    raw,
+                    None,
+                    ffi::GConnectFlags::G_CONNECT_AFTER,
+                );
+            };
+
+            Ok(out)
+        } else {
+            // `out.0` is still null on failure, so there's nothing to attach
+            // to; free the boxed buffer ourselves instead of leaking it.
+            unsafe { drop(Box::from_raw(raw as *mut Box<[u8]>)) };
+
+            Err(err::Error::from_vips("vips_thumbnail_buffer"))
+        }
+    }
+
     pub fn resize(
         &self,
         scale: f64,
@@ -235,7 +691,7 @@ impl Image {
 
         match ret {
             0 => Ok(out),
-            _ => Err(err::Error::from_vips()),
+            _ => Err(err::Error::from_vips("vips_resize")),
         }
     }
 
@@ -268,7 +724,7 @@ impl Image {
 
         match ret {
             0 => Ok(out),
-            _ => Err(err::Error::from_vips()),
+            _ => Err(err::Error::from_vips("vips_crop")),
         }
     }
 
@@ -279,7 +735,7 @@ impl Image {
 
         match ret {
             0 => Ok(out),
-            _ => Err(err::Error::from_vips()),
+            _ => Err(err::Error::from_vips("vips_rot")),
         }
     }
 
@@ -288,7 +744,7 @@ impl Image {
         let ret = unsafe { ffi::vips_image_write_to_file(self.0, path.as_ptr(), NULL_TERM) };
         match ret {
             0 => Ok(()),
-            _ => Err(err::Error::from_vips()),
+            _ => Err(err::Error::from_vips("vips_image_write_to_file")),
         }
     }
 
@@ -312,7 +768,7 @@ impl Image {
 
             match ret {
                 0 => Ok(out),
-                _ => Err(err::Error::from_vips()),
+                _ => Err(err::Error::from_vips("vips_image_write_to_buffer")),
             }
         }
     }
@@ -330,3 +786,185 @@ impl Image {
 
 unsafe impl Send for Image {}
 unsafe impl Sync for Image {}
+
+// Fundamental GType ids are stable (`fundamental << G_TYPE_FUNDAMENTAL_SHIFT`);
+// the dynamic ones (image, array) come from libvips' own registration calls.
+const G_TYPE_BOOLEAN: ffi::GType = 5 << 2;
+const G_TYPE_INT: ffi::GType = 6 << 2;
+const G_TYPE_DOUBLE: ffi::GType = 15 << 2;
+const G_TYPE_STRING: ffi::GType = 16 << 2;
+
+/// A value to bind to a named property of a [`Operation`]. Each variant maps to
+/// the matching `g_value_set_*` (`vips_value_set_array_double` for a double
+/// array) so any scalar, string, image, or double-array property of a libvips
+/// operation can be driven generically.
+pub enum Value {
+    Int(i32),
+    Double(f64),
+    Bool(bool),
+    Str(String),
+    Image(Image),
+    DoubleArray(Vec<f64>),
+}
+
+impl From<i32> for Value {
+    fn from(t: i32) -> Self {
+        Value::Int(t)
+    }
+}
+impl From<f64> for Value {
+    fn from(t: f64) -> Self {
+        Value::Double(t)
+    }
+}
+impl From<bool> for Value {
+    fn from(t: bool) -> Self {
+        Value::Bool(t)
+    }
+}
+impl From<&str> for Value {
+    fn from(t: &str) -> Self {
+        Value::Str(t.to_owned())
+    }
+}
+impl From<String> for Value {
+    fn from(t: String) -> Self {
+        Value::Str(t)
+    }
+}
+impl From<Image> for Value {
+    fn from(t: Image) -> Self {
+        Value::Image(t)
+    }
+}
+impl From<Vec<f64>> for Value {
+    fn from(t: Vec<f64>) -> Self {
+        Value::DoubleArray(t)
+    }
+}
+
+/// A generic passthrough to any libvips operation, driven through the
+/// `vips_operation_new` / `g_object_set` / `vips_cache_operation_build` /
+/// `g_object_get` cycle. Use it to reach operations that have no hand-written
+/// wrapper:
+///
+/// ```no_run
+/// # use rips::{Image, Operation};
+/// # fn run(image: &Image) -> rips::err::Result<()> {
+/// let blurred = Operation::new("gaussblur")?
+///     .set("sigma", 3.0)?
+///     .set("min_ampl", 0.2)?
+///     .run(image)?;
+/// # Ok(()) }
+/// ```
+pub struct Operation(*mut ffi::VipsOperation);
+
+impl Drop for Operation {
+    fn drop(&mut self) {
+        unsafe {
+            ffi::g_object_unref(self.0 as *mut c_void);
+        }
+    }
+}
+
+impl Operation {
+    pub fn new<S: Into<Vec<u8>>>(name: S) -> err::Result<Operation> {
+        initialize();
+
+        let name = CString::new(name)?;
+        let ptr = unsafe { ffi::vips_operation_new(name.as_ptr()) };
+        if ptr.is_null() {
+            Err(err::Error::from_vips("vips_operation_new"))
+        } else {
+            Ok(Operation(ptr))
+        }
+    }
+
+    /// Bind a value to the named operation property.
+    pub fn set<S: Into<Vec<u8>>, V: Into<Value>>(self, name: S, value: V) -> err::Result<Operation> {
+        let name = CString::new(name)?;
+        self.set_value(name.as_ptr(), value.into())?;
+        Ok(self)
+    }
+
+    fn set_value(&self, name: *const c_char, value: Value) -> err::Result<()> {
+        unsafe {
+            let mut gvalue: ffi::GValue = std::mem::zeroed();
+            match value {
+                Value::Int(v) => {
+                    ffi::g_value_init(&mut gvalue, G_TYPE_INT);
+                    ffi::g_value_set_int(&mut gvalue, v);
+                }
+                Value::Double(v) => {
+                    ffi::g_value_init(&mut gvalue, G_TYPE_DOUBLE);
+                    ffi::g_value_set_double(&mut gvalue, v);
+                }
+                Value::Bool(v) => {
+                    ffi::g_value_init(&mut gvalue, G_TYPE_BOOLEAN);
+                    ffi::g_value_set_boolean(&mut gvalue, v as c_int);
+                }
+                Value::Str(v) => {
+                    let v = CString::new(v)?;
+                    ffi::g_value_init(&mut gvalue, G_TYPE_STRING);
+                    ffi::g_value_set_string(&mut gvalue, v.as_ptr());
+                }
+                Value::Image(v) => {
+                    ffi::g_value_init(&mut gvalue, ffi::vips_image_get_type());
+                    ffi::g_value_set_object(&mut gvalue, v.0 as *mut c_void);
+                }
+                Value::DoubleArray(v) => {
+                    ffi::g_value_init(&mut gvalue, ffi::vips_array_double_get_type());
+                    ffi::vips_value_set_array_double(&mut gvalue, v.as_ptr(), v.len() as c_int);
+                }
+            }
+            ffi::g_object_set_property(self.0 as *mut ffi::GObject, name, &gvalue);
+            ffi::g_value_unset(&mut gvalue);
+        }
+        Ok(())
+    }
+
+    /// Read a named image-valued output back off the (built) operation.
+    pub fn get_image<S: Into<Vec<u8>>>(&self, name: S) -> err::Result<Image> {
+        let name = CString::new(name)?;
+        unsafe {
+            let mut gvalue: ffi::GValue = std::mem::zeroed();
+            ffi::g_value_init(&mut gvalue, ffi::vips_image_get_type());
+            ffi::g_object_get_property(self.0 as *mut ffi::GObject, name.as_ptr(), &mut gvalue);
+            let ptr = ffi::g_value_get_object(&mut gvalue) as *mut ffi::VipsImage;
+            let out = if ptr.is_null() {
+                Err(err::Error::from_vips("g_object_get"))
+            } else {
+                // `get_object` hands back a borrow; take our own ref so the
+                // returned `Image` owns it across the `g_value_unset` below.
+                ffi::g_object_ref(ptr as *mut c_void);
+                Ok(Image(ptr))
+            };
+            ffi::g_value_unset(&mut gvalue);
+            out
+        }
+    }
+
+    /// Bind `image` to the canonical `in` argument, build the operation through
+    /// the cache, and return its `out` image.
+    pub fn run(self, image: &Image) -> err::Result<Image> {
+        let in_name = CStr::from_bytes_with_nul(b"in\0").unwrap();
+        // `set_value` takes ownership of the wrapped `Image` and drops it once
+        // the `GValue` holds its own ref; take a ref up front so that drop
+        // balances and the caller's borrowed handle survives.
+        unsafe { ffi::g_object_ref(image.0 as *mut c_void) };
+        self.set_value(in_name.as_ptr(), Value::Image(Image(image.0)))?;
+
+        let built = unsafe { ffi::vips_cache_operation_build(self.0) };
+        if built.is_null() {
+            return Err(err::Error::from_vips("vips_cache_operation_build"));
+        }
+
+        // `build` returns the cached (possibly substitute) operation; adopt it
+        // and drop our unbuilt original.
+        let built = Operation(built);
+        built.get_image("out")
+    }
+}
+
+unsafe impl Send for Operation {}
+unsafe impl Sync for Operation {}